@@ -0,0 +1,281 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Automatic timing correction that aligns a subtitle file's cue-presence signal to a
+//! reference subtitle file, without looking at either file's text.
+
+use crate::errors::Result as SubtitleParserResult;
+use crate::timetypes::{TimeDelta, TimePoint, TimeSpan};
+use crate::timing::Retime;
+use crate::{SubtitleEntry, SubtitleFileInterface};
+
+/// Width of one time bin used when quantizing the cue-presence signal, in milliseconds.
+const BIN_MS: i64 = 10;
+
+/// Bound of the offset search window in either direction, in milliseconds.
+const MAX_OFFSET_MS: i64 = 60_000;
+
+/// Step between candidate offsets considered by the drift DP, in milliseconds. Coarser than
+/// `BIN_MS` so the DP over `chunks * candidates^2` stays cheap.
+const DP_STEP_MS: i64 = 200;
+
+/// Cost, per millisecond of jump, of picking a different offset for adjacent chunks. Keeps
+/// the drift-corrected result a smooth piecewise-linear warp instead of erratic per-chunk
+/// offsets.
+const CONTINUITY_PENALTY_PER_MS: f64 = 0.01;
+
+/// The timing correction [`Align::align_to`]/[`Align::align_to_split`] computed and applied.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Correction {
+    /// a single, file-wide delta
+    Global(TimeDelta),
+
+    /// one delta per contiguous chunk of the timeline, earliest chunk first
+    Split(Vec<TimeDelta>),
+}
+
+/// Aligns a subtitle file's timings to a reference file's, using only where cues are active
+/// on the timeline (not their text).
+pub trait Align: SubtitleFileInterface {
+    /// Finds the single offset that maximizes the cross-correlation between this file's and
+    /// `reference`'s cue-presence signal over a `±60s` search window, then shifts every entry
+    /// by it.
+    fn align_to(&mut self, reference: &dyn SubtitleFileInterface) -> SubtitleParserResult<Correction> {
+        let mine = self.get_subtitle_entries()?;
+        let theirs = reference.get_subtitle_entries()?;
+
+        let a = presence_intervals(&mine);
+        let b = presence_intervals(&theirs);
+        let (start_bin, end_bin) = bin_range(&a, &b);
+
+        let offset_bins = best_offset(&a, &b, start_bin, end_bin, -max_offset_bins()..=max_offset_bins());
+        let delta = TimeDelta::from_msecs(offset_bins * BIN_MS);
+
+        self.shift(delta)?;
+
+        Ok(Correction::Global(delta))
+    }
+
+    /// Like [`Align::align_to`], but splits the timeline into `chunks` contiguous segments
+    /// and solves a best offset per segment, using dynamic programming to penalize jumps
+    /// between adjacent segments. The result is a piecewise-linear warp that tracks drift
+    /// instead of a single shift.
+    fn align_to_split(&mut self, reference: &dyn SubtitleFileInterface, chunks: usize) -> SubtitleParserResult<Correction> {
+        assert!(chunks > 0, "align_to_split needs at least one chunk");
+
+        let mut mine = self.get_subtitle_entries()?;
+        let theirs = reference.get_subtitle_entries()?;
+
+        let a = presence_intervals(&mine);
+        let b = presence_intervals(&theirs);
+        let (start_bin, end_bin) = bin_range(&a, &b);
+        let chunk_bins = ((end_bin - start_bin) as f64 / chunks as f64).ceil() as i64;
+
+        let candidates: Vec<i64> = (-max_offset_bins()..=max_offset_bins()).step_by((DP_STEP_MS / BIN_MS) as usize).collect();
+
+        let chunk_scores: Vec<Vec<i64>> = (0..chunks as i64)
+            .map(|c| {
+                let chunk_start = start_bin + c * chunk_bins;
+                let chunk_end = (chunk_start + chunk_bins).min(end_bin);
+                candidates.iter().map(|&k| score(&a, &b, k, chunk_start, chunk_end)).collect()
+            })
+            .collect();
+
+        let offsets_ms = solve_drift(&chunk_scores, &candidates).into_iter().map(|bins| bins * BIN_MS).collect::<Vec<_>>();
+
+        for entry in &mut mine {
+            let bin = entry.timespan.start.msecs() / BIN_MS;
+            let chunk = (((bin - start_bin) / chunk_bins).max(0) as usize).min(chunks - 1);
+            entry.timespan = shift_span(entry.timespan, TimeDelta::from_msecs(offsets_ms[chunk]));
+        }
+
+        self.update_subtitle_entries(&mine)?;
+
+        Ok(Correction::Split(offsets_ms.into_iter().map(TimeDelta::from_msecs).collect()))
+    }
+}
+
+impl<T: SubtitleFileInterface + ?Sized> Align for T {}
+
+fn max_offset_bins() -> i64 {
+    MAX_OFFSET_MS / BIN_MS
+}
+
+fn shift_span(span: TimeSpan, delta: TimeDelta) -> TimeSpan {
+    TimeSpan::new(span.start + delta, span.end + delta)
+}
+
+/// The cue-presence intervals (in milliseconds, sorted by start, merged so none overlap),
+/// i.e. the `1` regions of the step function `a(t)`/`b(t)` described in the alignment
+/// algorithm. Merging up front is what lets `score`'s sweep count each covered millisecond
+/// once instead of once per overlapping cue (VTT commonly has overlapping cues, e.g.
+/// concurrent speaker lines).
+fn presence_intervals(entries: &[SubtitleEntry]) -> Vec<(i64, i64)> {
+    let mut intervals: Vec<(i64, i64)> = entries.iter().map(|entry| (entry.timespan.start.msecs(), entry.timespan.end.msecs())).collect();
+    intervals.sort_unstable_by_key(|&(start, _)| start);
+
+    let mut merged: Vec<(i64, i64)> = Vec::with_capacity(intervals.len());
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+}
+
+fn bin_range(a: &[(i64, i64)], b: &[(i64, i64)]) -> (i64, i64) {
+    let min_ms = a.iter().chain(b).map(|&(start, _)| start).min().unwrap_or(0);
+    let max_ms = a.iter().chain(b).map(|&(_, end)| end).max().unwrap_or(0);
+
+    (min_ms / BIN_MS, max_ms / BIN_MS + 1)
+}
+
+/// `score(k) = Σ_t a(t) · b(t+k)` over the bin range `[start_bin, end_bin)`, computed as the
+/// total overlap (in bins) between `a`'s intervals (clipped to that range) and `b`'s
+/// intervals each shifted *back* by `k` (`b(t+k)` is 1 exactly where `t` sits in a `b`
+/// interval shifted by `-k`). `a`/`b` must be merged, non-overlapping and sorted by start
+/// (see `presence_intervals`). This is a two-pointer sweep over the cues themselves — O(|a| +
+/// |b|) — rather than a scan over every bin of the timeline, which is what keeps scoring
+/// tractable on feature-length tracks.
+fn score(a: &[(i64, i64)], b: &[(i64, i64)], offset_bins: i64, start_bin: i64, end_bin: i64) -> i64 {
+    let window_start = start_bin * BIN_MS;
+    let window_end = end_bin * BIN_MS;
+    let offset_ms = offset_bins * BIN_MS;
+
+    let mut total_ms = 0i64;
+    let mut j = 0usize;
+
+    for &(a_start, a_end) in a {
+        let a_start = a_start.max(window_start);
+        let a_end = a_end.min(window_end);
+        if a_end <= a_start {
+            continue;
+        }
+
+        while j < b.len() && b[j].1 - offset_ms <= a_start {
+            j += 1;
+        }
+
+        let mut k = j;
+        while k < b.len() && b[k].0 - offset_ms < a_end {
+            let overlap_start = a_start.max(b[k].0 - offset_ms);
+            let overlap_end = a_end.min(b[k].1 - offset_ms);
+            total_ms += (overlap_end - overlap_start).max(0);
+            k += 1;
+        }
+    }
+
+    total_ms / BIN_MS
+}
+
+fn best_offset(a: &[(i64, i64)], b: &[(i64, i64)], start_bin: i64, end_bin: i64, candidate_bins: std::ops::RangeInclusive<i64>) -> i64 {
+    candidate_bins.max_by_key(|&k| score(a, b, k, start_bin, end_bin)).unwrap_or(0)
+}
+
+/// Picks one candidate-offset index per chunk maximizing total score minus the continuity
+/// penalty between adjacent chunks, via dynamic programming, and returns the chosen offsets
+/// in bins, earliest chunk first.
+fn solve_drift(chunk_scores: &[Vec<i64>], candidates: &[i64]) -> Vec<i64> {
+    let mut dp: Vec<f64> = chunk_scores[0].iter().map(|&s| s as f64).collect();
+    let mut backtrack: Vec<Vec<usize>> = Vec::with_capacity(chunk_scores.len());
+
+    for scores in &chunk_scores[1..] {
+        let mut next_dp = vec![f64::MIN; candidates.len()];
+        let mut next_back = vec![0usize; candidates.len()];
+
+        for (k_idx, &k) in candidates.iter().enumerate() {
+            for (prev_idx, &prev_k) in candidates.iter().enumerate() {
+                let penalty = CONTINUITY_PENALTY_PER_MS * ((k - prev_k).abs() * BIN_MS) as f64;
+                let value = dp[prev_idx] - penalty;
+
+                if value > next_dp[k_idx] {
+                    next_dp[k_idx] = value;
+                    next_back[k_idx] = prev_idx;
+                }
+            }
+
+            next_dp[k_idx] += scores[k_idx] as f64;
+        }
+
+        backtrack.push(next_back);
+        dp = next_dp;
+    }
+
+    let mut best_idx = (0..candidates.len()).max_by(|&i, &j| dp[i].partial_cmp(&dp[j]).unwrap()).unwrap_or(0);
+    let mut offsets = vec![0i64; chunk_scores.len()];
+    offsets[chunk_scores.len() - 1] = candidates[best_idx];
+
+    for (chunk, back) in backtrack.iter().enumerate().rev() {
+        best_idx = back[best_idx];
+        offsets[chunk] = candidates[best_idx];
+    }
+
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::vtt::VttFile;
+
+    fn file_with_cues(cues: &[(i64, i64)]) -> VttFile {
+        VttFile::create(
+            cues.iter()
+                .map(|&(start, end)| (TimeSpan::new(TimePoint::from_msecs(start), TimePoint::from_msecs(end)), "x".to_string()))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn align_to_finds_global_offset_test() {
+        let reference = file_with_cues(&[(1000, 2000), (5000, 6000), (9000, 9500)]);
+        let mut mine = file_with_cues(&[(1700, 2700), (5700, 6700), (9700, 10200)]);
+
+        let correction = mine.align_to(&reference).unwrap();
+        assert_eq!(correction, Correction::Global(TimeDelta::from_msecs(-700)));
+
+        let entries = mine.get_subtitle_entries().unwrap();
+        assert_eq!(entries[0].timespan, TimeSpan::new(TimePoint::from_msecs(1000), TimePoint::from_msecs(2000)));
+    }
+
+    #[test]
+    fn presence_intervals_merges_overlapping_cues_test() {
+        let entries = vec![
+            SubtitleEntry::new(TimeSpan::new(TimePoint::from_msecs(0), TimePoint::from_msecs(100)), "a".to_string()),
+            SubtitleEntry::new(TimeSpan::new(TimePoint::from_msecs(10), TimePoint::from_msecs(20)), "b".to_string()),
+        ];
+
+        // A naive "does an interval start at-or-before t" lookup would miss that (0, 100)
+        // still covers t=50 once (10, 20) sorts in front of it; merging up front avoids that.
+        assert_eq!(presence_intervals(&entries), vec![(0, 100)]);
+    }
+
+    #[test]
+    fn align_to_finds_offset_with_overlapping_cues_test() {
+        let reference = file_with_cues(&[(0, 100), (10, 20), (5000, 6000)]);
+        let mut mine = file_with_cues(&[(300, 400), (310, 320), (5300, 6300)]);
+
+        let correction = mine.align_to(&reference).unwrap();
+        assert_eq!(correction, Correction::Global(TimeDelta::from_msecs(-300)));
+    }
+
+    #[test]
+    fn align_to_split_tracks_drift_test() {
+        let reference = file_with_cues(&[(0, 2000), (10000, 12000)]);
+        let mut mine = file_with_cues(&[(400, 2400), (11000, 13000)]);
+
+        let correction = mine.align_to_split(&reference, 2).unwrap();
+        assert_eq!(
+            correction,
+            Correction::Split(vec![TimeDelta::from_msecs(-400), TimeDelta::from_msecs(-1000)])
+        );
+
+        let entries = mine.get_subtitle_entries().unwrap();
+        assert_eq!(entries[0].timespan, TimeSpan::new(TimePoint::from_msecs(0), TimePoint::from_msecs(2000)));
+        assert_eq!(entries[1].timespan, TimeSpan::new(TimePoint::from_msecs(10000), TimePoint::from_msecs(12000)));
+    }
+}
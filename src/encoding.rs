@@ -0,0 +1,26 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Encoding-aware construction alongside [`SubtitleFileInterface`]. It is implemented by
+//! [`VttFile`](crate::formats::vtt::VttFile) only; the other subtitle file types do not
+//! implement it.
+
+use crate::errors::Result as SubtitleParserResult;
+use crate::SubtitleFileInterface;
+
+use encoding_rs::Encoding;
+
+/// Parses a subtitle file from raw bytes, decoding them with `encoding` if given, or
+/// auto-detecting the character encoding otherwise.
+///
+/// This is split out from [`SubtitleFileInterface`] itself, rather than added to it as a
+/// method, because it is a constructor (`Self: Sized`): `SubtitleFileInterface` is used as
+/// `&dyn SubtitleFileInterface` throughout (see [`crate::timing::Retime`],
+/// [`crate::align::Align`]), and a non-object-safe method would break that.
+pub trait SubtitleFile: SubtitleFileInterface + Sized {
+    /// Parses `data`, decoding it with `encoding` if given, or auto-detecting the character
+    /// encoding otherwise (sniffing a BOM first, then falling back to charset detection on
+    /// the content).
+    fn parse_bytes(data: &[u8], encoding: Option<&'static Encoding>) -> SubtitleParserResult<Self>;
+}
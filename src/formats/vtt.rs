@@ -11,6 +11,9 @@ use failure::ResultExt;
 
 use itertools::Itertools;
 
+use chardetng::EncodingDetector;
+use encoding_rs::Encoding;
+
 use crate::timetypes::{TimePoint, TimeSpan};
 
 type Result<T> = std::result::Result<T, Error>;
@@ -31,6 +34,12 @@ pub mod errors {
 
         #[fail(display = "parse error at line `{}`", line_num)]
         ErrorAtLine { line_num: usize },
+
+        #[fail(display = "invalid or truncated MP4 box structure")]
+        InvalidMp4Data,
+
+        #[fail(display = "unsupported MP4 fragment layout: {}", reason)]
+        UnsupportedMp4Layout { reason: &'static str },
     }
 }
 
@@ -49,6 +58,13 @@ struct VttLine {
     /// index/number of line
     index: i64,
 
+    /// the textual cue identifier as found before the timespan line, if any
+    identifier: Option<String>,
+
+    /// the verbatim cue settings (`line:90% position:50% align:middle ...`) found after the
+    /// end timestamp, if any
+    settings: String,
+
     /// the dialog/text lines of the `SrtLine`
     texts: Vec<String>,
 }
@@ -58,12 +74,138 @@ impl VttFile {
     pub fn parse(s: &str) -> SubtitleParserResult<VttFile> {
         Ok(Self::parse_file(s).with_context(|_| crate::ErrorKind::ParsingError)?)
     }
+
+    /// Parses a `.vtt` file from raw bytes, decoding them with `encoding` if given, or
+    /// auto-detecting the character encoding otherwise (sniffing a BOM first, then falling
+    /// back to charset detection on the content).
+    ///
+    /// Also available as [`SubtitleFile::parse_bytes`](crate::encoding::SubtitleFile::parse_bytes),
+    /// for callers that want to go through the common, format-agnostic entry point.
+    pub fn parse_bytes(data: &[u8], encoding: Option<&'static Encoding>) -> SubtitleParserResult<VttFile> {
+        let encoding = encoding
+            .or_else(|| Encoding::for_bom(data).map(|(encoding, _bom_length)| encoding))
+            .unwrap_or_else(|| Self::detect_encoding(data));
+
+        let (decoded, _encoding_used, _had_errors) = encoding.decode(data);
+
+        Self::parse(&decoded)
+    }
+
+    /// Guesses the character encoding of `data` when no BOM is present.
+    fn detect_encoding(data: &[u8]) -> &'static Encoding {
+        let mut detector = EncodingDetector::new();
+        detector.feed(data, true);
+        detector.guess(None, true)
+    }
+}
+
+impl crate::encoding::SubtitleFile for VttFile {
+    fn parse_bytes(data: &[u8], encoding: Option<&'static Encoding>) -> SubtitleParserResult<Self> {
+        Self::parse_bytes(data, encoding)
+    }
 }
 
 /// Implements parse functions.
 impl VttFile {
     fn parse_file(i: &str) -> Result<VttFile> {
-        unimplemented!();
+        let mut lines = i.lines().enumerate().map(|(n, line)| (n + 1, line.trim_end_matches('\r')));
+
+        // the mandatory `WEBVTT` header line.
+        let (header_line_num, header) = lines.next().ok_or(ErrorKind::ErrorAtLine { line_num: 1 })?;
+        if !header.starts_with("WEBVTT") {
+            return Err(ErrorKind::ErrorAtLine { line_num: header_line_num }.into());
+        }
+
+        // any remaining header text up to the first blank line. `NOTE`/`STYLE`/`REGION` blocks
+        // after that line are not skipped; they are read as cue blocks.
+        for (_, line) in &mut lines {
+            if line.trim().is_empty() {
+                break;
+            }
+        }
+
+        let mut result = Vec::new();
+        let mut auto_index: i64 = 0;
+        let mut block: Vec<(usize, &str)> = Vec::new();
+
+        for (line_num, line) in lines.chain(std::iter::once((0, ""))) {
+            if line.trim().is_empty() {
+                if !block.is_empty() {
+                    auto_index += 1;
+                    result.push(Self::parse_block(&block, auto_index)?);
+                    block.clear();
+                }
+                continue;
+            }
+
+            block.push((line_num, line));
+        }
+
+        Ok(VttFile { v: result })
+    }
+
+    /// Parses a single cue block (the lines between two blank lines).
+    fn parse_block(block: &[(usize, &str)], auto_index: i64) -> Result<VttLine> {
+        let mut idx = 0;
+
+        let identifier = if Self::parse_timestamp_line(block[idx].1).is_none() {
+            idx += 1;
+            Some(block[0].1)
+        } else {
+            None
+        };
+
+        let &(_, ts_line) = block
+            .get(idx)
+            .ok_or_else(|| ErrorKind::ErrorAtLine { line_num: block[idx.saturating_sub(1)].0 })?;
+
+        let (timespan, settings) =
+            Self::parse_timestamp_line(ts_line).ok_or(ErrorKind::ExpectedTimestampLine { line: ts_line.to_string() })?;
+
+        let texts = block[idx + 1..].iter().map(|&(_, line)| line.to_string()).collect();
+
+        let index = identifier.and_then(|id| id.trim().parse().ok()).unwrap_or(auto_index);
+        let identifier = identifier.map(str::to_string);
+
+        Ok(VttLine {
+            timespan,
+            index,
+            identifier,
+            settings,
+            texts,
+        })
+    }
+
+    /// Parses a `HH:MM:SS.mmm --> HH:MM:SS.mmm [settings...]` line, leniently accepting `,`
+    /// as the millisecond separator and two-field `MM:SS.mmm` timestamps. Returns the
+    /// timespan together with the verbatim settings text that follows the end timestamp.
+    fn parse_timestamp_line(line: &str) -> Option<(TimeSpan, String)> {
+        let (start, end) = line.split_once("-->")?;
+
+        let start = Self::parse_timepoint(start.trim())?;
+
+        let end = end.trim_start();
+        let end_token_len = end.find(char::is_whitespace).unwrap_or(end.len());
+        let (end_token, settings) = end.split_at(end_token_len);
+        let end = Self::parse_timepoint(end_token)?;
+
+        Some((TimeSpan::new(start, end), settings.trim().to_string()))
+    }
+
+    /// Parses a single `HH:MM:SS.mmm`/`MM:SS.mmm` timestamp.
+    fn parse_timepoint(s: &str) -> Option<TimePoint> {
+        let s = s.replace(',', ".");
+        let (time_part, msecs_part) = s.split_once('.')?;
+        let msecs: i64 = msecs_part.parse().ok()?;
+
+        let fields: Vec<&str> = time_part.split(':').collect();
+        let (hours, mins, secs): (i64, i64, i64) = match *fields.as_slice() {
+            [h, m, s] => (h.parse().ok()?, m.parse().ok()?, s.parse().ok()?),
+            [m, s] => (0, m.parse().ok()?, s.parse().ok()?),
+            _ => return None,
+        };
+
+        Some(TimePoint::from_msecs(((hours * 60 + mins) * 60 + secs) * 1000 + msecs))
     }
 }
 
@@ -95,11 +237,22 @@ impl SubtitleFileInterface for VttFile {
         let timepoint_to_str =
             |t: TimePoint| -> String { format!("{:02}:{:02}:{:02}.{:03}", t.hours(), t.mins_comp(), t.secs_comp(), t.msecs_comp()) };
         let line_to_str = |line: &VttLine| -> String {
+            let identifier = match line.identifier {
+                Some(ref identifier) => identifier.clone(),
+                None => line.index.to_string(),
+            };
+            let settings = if line.settings.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", line.settings)
+            };
+
             format!(
-                "{}\n{} --> {}\n{}\n\n",
-                line.index,
+                "{}\n{} --> {}{}\n{}\n\n",
+                identifier,
                 timepoint_to_str(line.timespan.start),
                 timepoint_to_str(line.timespan.end),
+                settings,
                 line.texts.join("\n")
             )
         };
@@ -121,6 +274,8 @@ impl VttFile {
             .map(|(i, (ts, text))| VttLine {
                 index: i as i64 + 1,
                 timespan: ts,
+                identifier: None,
+                settings: String::new(),
                 texts: text.lines().map(str::to_string).collect(),
             })
             .collect();
@@ -129,6 +284,270 @@ impl VttFile {
     }
 }
 
+impl VttFile {
+    /// Parses WebVTT cues carried in a fragmented MP4 (`wvtt`/`vttc`) caption track, as
+    /// produced by HLS/DASH packagers. Walks the `moof`/`traf` boxes for each fragment's
+    /// sample durations (and `tfdt` base decode time), then slices the matching `mdat` bytes
+    /// into samples and reads each sample's `vttc` children (`iden`/`sttg`/`payl`).
+    pub fn parse_mp4(data: &[u8]) -> SubtitleParserResult<VttFile> {
+        Ok(Self::parse_mp4_data(data).with_context(|_| crate::ErrorKind::ParsingError)?)
+    }
+
+    fn parse_mp4_data(data: &[u8]) -> Result<VttFile> {
+        let timescale = match mp4::media_timescale(data) {
+            // a malformed/truncated `mdhd` can carry a present-but-zero timescale; reject it
+            // rather than dividing by it below.
+            Some(0) => return Err(ErrorKind::InvalidMp4Data.into()),
+            Some(timescale) => timescale as i64,
+            None => 1000,
+        };
+
+        let mut result = Vec::new();
+        let mut auto_index: i64 = 0;
+        let mut pending: Vec<mp4::PendingSample> = Vec::new();
+
+        for (kind, content) in mp4::boxes(data) {
+            if &kind == b"moof" {
+                pending = mp4::pending_samples(content)?;
+            } else if &kind == b"mdat" {
+                let mut offset = 0usize;
+
+                for sample in pending.drain(..) {
+                    let sample_bytes = content
+                        .get(offset..offset + sample.size)
+                        .ok_or(ErrorKind::InvalidMp4Data)?;
+                    offset += sample.size;
+
+                    let timespan = TimeSpan::new(
+                        TimePoint::from_msecs(sample.start * 1000 / timescale),
+                        TimePoint::from_msecs(sample.end * 1000 / timescale),
+                    );
+
+                    for (_, vttc) in mp4::boxes(sample_bytes).into_iter().filter(|&(kind, _)| &kind == b"vttc") {
+                        auto_index += 1;
+
+                        let mut identifier = None;
+                        let mut settings = String::new();
+                        let mut texts = Vec::new();
+
+                        for (child_kind, child_content) in mp4::boxes(vttc) {
+                            match &child_kind {
+                                b"iden" => identifier = Some(String::from_utf8_lossy(child_content).into_owned()),
+                                b"sttg" => settings = String::from_utf8_lossy(child_content).into_owned(),
+                                b"payl" => texts = String::from_utf8_lossy(child_content).lines().map(str::to_string).collect(),
+                                _ => {}
+                            }
+                        }
+
+                        let index = identifier.as_deref().and_then(|id| id.trim().parse().ok()).unwrap_or(auto_index);
+
+                        result.push(VttLine {
+                            timespan,
+                            index,
+                            identifier,
+                            settings,
+                            texts,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(VttFile { v: result })
+    }
+}
+
+/// Minimal ISOBMFF (MP4) box walker, just enough to read `wvtt`-carried WebVTT cues.
+mod mp4 {
+    use super::{ErrorKind, Result};
+    use std::convert::TryInto;
+
+    /// Box types that contain other boxes rather than raw data, and so are worth recursing
+    /// into when looking for a specific box type.
+    const CONTAINERS: &[[u8; 4]] = &[*b"moov", *b"trak", *b"mdia", *b"minf", *b"stbl", *b"mvex", *b"moof", *b"traf", *b"edts", *b"udta"];
+
+    pub struct PendingSample {
+        /// sample start time, in the track's media timescale
+        pub start: i64,
+
+        /// sample end time, in the track's media timescale
+        pub end: i64,
+
+        /// sample byte size within the `mdat` that follows this sample's `moof`
+        pub size: usize,
+    }
+
+    /// Splits `data` into a sequence of `(type, content)` boxes, handling the 32-bit,
+    /// 64-bit-extended (`size == 1`) and to-end-of-data (`size == 0`) size forms.
+    pub fn boxes(data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+        let mut result = Vec::new();
+        let mut pos = 0;
+
+        while pos + 8 <= data.len() {
+            let size32 = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+            let kind: [u8; 4] = data[pos + 4..pos + 8].try_into().unwrap();
+
+            let (header_len, box_size) = if size32 == 1 {
+                if pos + 16 > data.len() {
+                    break;
+                }
+                (16, u64::from_be_bytes(data[pos + 8..pos + 16].try_into().unwrap()) as usize)
+            } else if size32 == 0 {
+                (8, data.len() - pos)
+            } else {
+                (8, size32)
+            };
+
+            if box_size < header_len || pos + box_size > data.len() {
+                break;
+            }
+
+            result.push((kind, &data[pos + header_len..pos + box_size]));
+            pos += box_size;
+        }
+
+        result
+    }
+
+    /// Recursively collects the content of every box of type `kind` found in `data`.
+    fn find_recursive<'a>(data: &'a [u8], kind: &[u8; 4], out: &mut Vec<&'a [u8]>) {
+        for (box_kind, content) in boxes(data) {
+            if &box_kind == kind {
+                out.push(content);
+            }
+            if CONTAINERS.contains(&box_kind) {
+                find_recursive(content, kind, out);
+            }
+        }
+    }
+
+    /// The media timescale from the track's `mdhd` box (units per second).
+    pub fn media_timescale(data: &[u8]) -> Option<u32> {
+        let mut mdhd_boxes = Vec::new();
+        find_recursive(data, b"mdhd", &mut mdhd_boxes);
+        let mdhd = mdhd_boxes.first()?;
+
+        let timescale_offset = if mdhd[0] == 1 { 4 + 16 } else { 4 + 8 };
+        Some(u32::from_be_bytes(mdhd.get(timescale_offset..timescale_offset + 4)?.try_into().ok()?))
+    }
+
+    /// The fragment's base media decode time, from its `traf`'s `tfdt` box (`0` if absent).
+    fn base_media_decode_time(traf: &[u8]) -> Result<u64> {
+        let mut tfdt_boxes = Vec::new();
+        find_recursive(traf, b"tfdt", &mut tfdt_boxes);
+
+        let tfdt = match tfdt_boxes.first() {
+            Some(tfdt) => tfdt,
+            None => return Ok(0),
+        };
+
+        match tfdt.first().ok_or(ErrorKind::InvalidMp4Data)? {
+            1 => Ok(u64::from_be_bytes(tfdt.get(4..12).ok_or(ErrorKind::InvalidMp4Data)?.try_into().unwrap())),
+            _ => Ok(u32::from_be_bytes(tfdt.get(4..8).ok_or(ErrorKind::InvalidMp4Data)?.try_into().unwrap()) as u64),
+        }
+    }
+
+    /// Reads a `traf`'s `trun` sample durations/sizes, one entry per sample, and expands
+    /// them into `PendingSample`s using the fragment's base decode time as a starting point.
+    ///
+    /// Assumes a single-track, caption-only fragment whose `mdat` holds exactly this
+    /// fragment's samples back-to-back starting at its first byte — true of the HLS/DASH
+    /// `wvtt` segments this module targets, but not of a multiplexed fragment. A `trun` with
+    /// a non-zero `data_offset`, or more than one `traf` per fragment, would place samples
+    /// elsewhere in (or interleaved with other tracks' samples in) the `mdat`, which this
+    /// module has no way to locate; reject those rather than silently reading the wrong bytes.
+    pub fn pending_samples(moof_content: &[u8]) -> Result<Vec<PendingSample>> {
+        let mut trafs = Vec::new();
+        find_recursive(moof_content, b"traf", &mut trafs);
+
+        if trafs.len() > 1 {
+            return Err(ErrorKind::UnsupportedMp4Layout { reason: "multiple `traf` boxes in one fragment" }.into());
+        }
+
+        let mut samples = Vec::new();
+
+        for traf in trafs {
+            let mut t = base_media_decode_time(traf)? as i64;
+
+            let mut truns = Vec::new();
+            find_recursive(traf, b"trun", &mut truns);
+
+            for trun in truns {
+                let (data_offset, entries) = trun_entries(trun)?;
+                if data_offset.unwrap_or(0) != 0 {
+                    return Err(ErrorKind::UnsupportedMp4Layout { reason: "`trun` has a non-zero data_offset" }.into());
+                }
+
+                for (duration, size) in entries {
+                    let duration = duration.unwrap_or(0) as i64;
+                    samples.push(PendingSample {
+                        start: t,
+                        end: t + duration,
+                        size: size.unwrap_or(0) as usize,
+                    });
+                    t += duration;
+                }
+            }
+        }
+
+        Ok(samples)
+    }
+
+    /// A `trun` box's `data_offset` (if present) and its per-sample `(duration, size)` pairs,
+    /// per ISO/IEC 14496-12.
+    fn trun_entries(trun: &[u8]) -> Result<(Option<i32>, Vec<(Option<u32>, Option<u32>)>)> {
+        let read_u32 = |pos: usize| -> Result<u32> {
+            Ok(u32::from_be_bytes(trun.get(pos..pos + 4).ok_or(ErrorKind::InvalidMp4Data)?.try_into().unwrap()))
+        };
+
+        let flags = read_u32(0)? & 0x00ff_ffff;
+        let sample_count = read_u32(4)?;
+
+        let mut pos = 8;
+        let data_offset = if flags & 0x0000_0001 != 0 {
+            let v = read_u32(pos)? as i32;
+            pos += 4;
+            Some(v)
+        } else {
+            None
+        };
+        if flags & 0x0000_0004 != 0 {
+            pos += 4; // first_sample_flags
+        }
+
+        let mut entries = Vec::with_capacity(sample_count as usize);
+
+        for _ in 0..sample_count {
+            let duration = if flags & 0x0000_0100 != 0 {
+                let v = read_u32(pos)?;
+                pos += 4;
+                Some(v)
+            } else {
+                None
+            };
+
+            let size = if flags & 0x0000_0200 != 0 {
+                let v = read_u32(pos)?;
+                pos += 4;
+                Some(v)
+            } else {
+                None
+            };
+
+            if flags & 0x0000_0400 != 0 {
+                pos += 4; // sample_flags
+            }
+            if flags & 0x0000_0800 != 0 {
+                pos += 4; // sample_composition_time_offset
+            }
+
+            entries.push((duration, size));
+        }
+
+        Ok((data_offset, entries))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -153,5 +572,248 @@ mod tests {
         let expected = "WEBVTT\n\n1\n00:00:01.500 --> 00:00:03.700\nline1\n\n2\n00:00:04.500 --> 00:00:08.700\nline2\n\n".to_string();
         assert_eq!(data_string, expected);
     }
+
+    #[test]
+    fn parse_vtt_test() {
+        use crate::timetypes::{TimePoint, TimeSpan};
+        use crate::SubtitleFileInterface;
+
+        let data = "WEBVTT\n\n1\n00:00:01.500 --> 00:00:03.700\nline1\n\n2\n00:00:04.500 --> 00:00:08.700\nline2\n\n";
+        let file = super::VttFile::parse(data).unwrap();
+
+        let entries = file.get_subtitle_entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].timespan, TimeSpan::new(TimePoint::from_msecs(1500), TimePoint::from_msecs(3700)));
+        assert_eq!(entries[0].line, Some("line1".to_string()));
+        assert_eq!(entries[1].timespan, TimeSpan::new(TimePoint::from_msecs(4500), TimePoint::from_msecs(8700)));
+        assert_eq!(entries[1].line, Some("line2".to_string()));
+    }
+
+    #[test]
+    fn parse_vtt_without_identifiers_test() {
+        use crate::SubtitleFileInterface;
+
+        let data = "WEBVTT\n\n00:00:01.500 --> 00:00:03.700\nline1\n\n00:00:04.500 --> 00:00:08.700\nline2\n\n";
+        let file = super::VttFile::parse(data).unwrap();
+
+        let entries = file.get_subtitle_entries().unwrap();
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[test]
+    fn parse_vtt_lenient_comma_and_short_timestamps_test() {
+        use crate::SubtitleFileInterface;
+
+        let data = "WEBVTT\n\n1\n01:01,500 --> 01:03,700\nline1\n\n";
+        let file = super::VttFile::parse(data).unwrap();
+
+        let entries = file.get_subtitle_entries().unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn parse_vtt_multiline_text_test() {
+        use crate::SubtitleFileInterface;
+
+        let data = "WEBVTT\n\n1\n00:00:01.500 --> 00:00:03.700\nline1\nline2\n\n";
+        let file = super::VttFile::parse(data).unwrap();
+
+        let entries = file.get_subtitle_entries().unwrap();
+        assert_eq!(entries[0].line, Some("line1\nline2".to_string()));
+    }
+
+    #[test]
+    fn parse_vtt_round_trip_test() {
+        use crate::SubtitleFileInterface;
+
+        let data = "WEBVTT\n\n1\n00:00:01.500 --> 00:00:03.700\nline1\n\n2\n00:00:04.500 --> 00:00:08.700\nline2\n\n";
+        let file = super::VttFile::parse(data).unwrap();
+
+        let data_string = String::from_utf8(file.to_data().unwrap()).unwrap();
+        assert_eq!(data_string, data);
+    }
+
+    #[test]
+    fn parse_vtt_identifier_and_settings_round_trip_test() {
+        let data = "WEBVTT\n\ncue-1\n00:00:01.500 --> 00:00:03.700 line:90% position:50% align:middle\nline1\n\n";
+        let file = super::VttFile::parse(data).unwrap();
+
+        let data_string = String::from_utf8(file.to_data().unwrap()).unwrap();
+        assert_eq!(data_string, data);
+    }
+
+    #[test]
+    fn parse_vtt_missing_header_test() {
+        let err = super::VttFile::parse("1\n00:00:01.500 --> 00:00:03.700\nline1\n\n");
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn parse_vtt_missing_timestamp_test() {
+        let err = super::VttFile::parse("WEBVTT\n\nnot a timestamp\n\n");
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn parse_bytes_with_utf8_bom_test() {
+        use crate::SubtitleFileInterface;
+
+        let mut data = b"\xEF\xBB\xBF".to_vec();
+        data.extend_from_slice(b"WEBVTT\n\n1\n00:00:01.500 --> 00:00:03.700\nline1\n\n");
+
+        let file = super::VttFile::parse_bytes(&data, None).unwrap();
+        let entries = file.get_subtitle_entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].line, Some("line1".to_string()));
+    }
+
+    #[test]
+    fn parse_bytes_with_explicit_encoding_test() {
+        use crate::SubtitleFileInterface;
+
+        let data = encoding_rs::WINDOWS_1252.encode("WEBVTT\n\n1\n00:00:01.500 --> 00:00:03.700\ncafé\n\n").0;
+
+        let file = super::VttFile::parse_bytes(&data, Some(encoding_rs::WINDOWS_1252)).unwrap();
+        let entries = file.get_subtitle_entries().unwrap();
+        assert_eq!(entries[0].line, Some("café".to_string()));
+    }
+
+    #[test]
+    fn parse_bytes_through_subtitle_file_trait_test() {
+        use crate::encoding::SubtitleFile;
+        use crate::SubtitleFileInterface;
+
+        let data = b"WEBVTT\n\n1\n00:00:01.500 --> 00:00:03.700\nline1\n\n";
+
+        let file: super::VttFile = SubtitleFile::parse_bytes(data, None).unwrap();
+        let entries = file.get_subtitle_entries().unwrap();
+        assert_eq!(entries[0].line, Some("line1".to_string()));
+    }
+
+    fn make_box(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut b = Vec::new();
+        b.extend_from_slice(&((content.len() + 8) as u32).to_be_bytes());
+        b.extend_from_slice(kind);
+        b.extend_from_slice(content);
+        b
+    }
+
+    #[test]
+    fn parse_mp4_test() {
+        use crate::timetypes::{TimePoint, TimeSpan};
+        use crate::SubtitleFileInterface;
+
+        let mdhd = {
+            let mut c = vec![0u8]; // version
+            c.extend_from_slice(&[0, 0, 0]); // flags
+            c.extend_from_slice(&0u32.to_be_bytes()); // creation_time
+            c.extend_from_slice(&0u32.to_be_bytes()); // modification_time
+            c.extend_from_slice(&1000u32.to_be_bytes()); // timescale
+            c.extend_from_slice(&0u32.to_be_bytes()); // duration
+            c
+        };
+        let moov = make_box(b"moov", &make_box(b"trak", &make_box(b"mdia", &make_box(b"mdhd", &mdhd))));
+
+        let tfdt = {
+            let mut c = vec![0u8, 0, 0, 0];
+            c.extend_from_slice(&0u32.to_be_bytes()); // base_media_decode_time
+            c
+        };
+
+        let mut vttc_children = Vec::new();
+        vttc_children.extend(make_box(b"iden", b"1"));
+        vttc_children.extend(make_box(b"payl", b"hello"));
+        let sample = make_box(b"vttc", &vttc_children);
+
+        let trun = {
+            let mut c = vec![0u8];
+            c.extend_from_slice(&[0x00, 0x03, 0x00]); // flags: duration + size present
+            c.extend_from_slice(&1u32.to_be_bytes()); // sample_count
+            c.extend_from_slice(&2000u32.to_be_bytes()); // sample duration
+            c.extend_from_slice(&(sample.len() as u32).to_be_bytes()); // sample size
+            c
+        };
+
+        let mut traf_bytes = Vec::new();
+        traf_bytes.extend(make_box(b"tfdt", &tfdt));
+        traf_bytes.extend(make_box(b"trun", &trun));
+
+        let moof = make_box(b"moof", &make_box(b"traf", &traf_bytes));
+        let mdat = make_box(b"mdat", &sample);
+
+        let mut data = Vec::new();
+        data.extend(moov);
+        data.extend(moof);
+        data.extend(mdat);
+
+        let file = super::VttFile::parse_mp4(&data).unwrap();
+        let entries = file.get_subtitle_entries().unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].timespan, TimeSpan::new(TimePoint::from_msecs(0), TimePoint::from_msecs(2000)));
+        assert_eq!(entries[0].line, Some("hello".to_string()));
+    }
+
+    #[test]
+    fn parse_mp4_truncated_tfdt_is_an_error_not_a_panic_test() {
+        let mut mdhd = [0u8; 20];
+        mdhd[12..16].copy_from_slice(&1000u32.to_be_bytes()); // timescale, so the error can only come from `tfdt`
+        let moov = make_box(b"moov", &make_box(b"trak", &make_box(b"mdia", &make_box(b"mdhd", &mdhd))));
+
+        // version 1 (64-bit base_media_decode_time) but truncated to 1 byte of payload.
+        let tfdt = vec![1u8];
+
+        let mut traf_bytes = Vec::new();
+        traf_bytes.extend(make_box(b"tfdt", &tfdt));
+
+        let moof = make_box(b"moof", &make_box(b"traf", &traf_bytes));
+
+        let mut data = Vec::new();
+        data.extend(moov);
+        data.extend(moof);
+
+        assert!(super::VttFile::parse_mp4(&data).is_err());
+    }
+
+    #[test]
+    fn parse_mp4_rejects_nonzero_trun_data_offset_test() {
+        let mdhd = {
+            let mut c = vec![0u8]; // version
+            c.extend_from_slice(&[0, 0, 0]); // flags
+            c.extend_from_slice(&0u32.to_be_bytes()); // creation_time
+            c.extend_from_slice(&0u32.to_be_bytes()); // modification_time
+            c.extend_from_slice(&1000u32.to_be_bytes()); // timescale
+            c.extend_from_slice(&0u32.to_be_bytes()); // duration
+            c
+        };
+        let moov = make_box(b"moov", &make_box(b"trak", &make_box(b"mdia", &make_box(b"mdhd", &mdhd))));
+
+        let tfdt = {
+            let mut c = vec![0u8, 0, 0, 0];
+            c.extend_from_slice(&0u32.to_be_bytes()); // base_media_decode_time
+            c
+        };
+
+        let trun = {
+            let mut c = vec![0u8];
+            c.extend_from_slice(&[0x00, 0x03, 0x01]); // flags: data_offset + duration + size present
+            c.extend_from_slice(&1u32.to_be_bytes()); // sample_count
+            c.extend_from_slice(&8u32.to_be_bytes()); // data_offset (nonzero: caption samples aren't first in `mdat`)
+            c.extend_from_slice(&2000u32.to_be_bytes()); // sample duration
+            c.extend_from_slice(&5u32.to_be_bytes()); // sample size
+            c
+        };
+
+        let mut traf_bytes = Vec::new();
+        traf_bytes.extend(make_box(b"tfdt", &tfdt));
+        traf_bytes.extend(make_box(b"trun", &trun));
+
+        let moof = make_box(b"moof", &make_box(b"traf", &traf_bytes));
+
+        let mut data = Vec::new();
+        data.extend(moov);
+        data.extend(moof);
+
+        assert!(super::VttFile::parse_mp4(&data).is_err());
+    }
 }
-// TODO: parser tests
@@ -0,0 +1,149 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Retiming helpers built on top of [`SubtitleFileInterface`], shared by every subtitle
+//! format instead of being reimplemented per-format.
+
+use std::ops::Range;
+
+use crate::errors::Result as SubtitleParserResult;
+use crate::timetypes::{TimeDelta, TimePoint, TimeSpan};
+use crate::SubtitleFileInterface;
+
+/// Selects which subtitle entries a [`Retime`] operation applies to.
+#[derive(Debug, Clone)]
+pub enum EntrySelector {
+    /// selects entries by their index into `get_subtitle_entries()`
+    Index(Range<usize>),
+
+    /// selects entries whose timespan overlaps the given window
+    TimeRange(TimeSpan),
+}
+
+impl EntrySelector {
+    fn matches(&self, index: usize, timespan: TimeSpan) -> bool {
+        match self {
+            EntrySelector::Index(range) => range.contains(&index),
+            EntrySelector::TimeRange(window) => timespan.start < window.end && timespan.end > window.start,
+        }
+    }
+}
+
+impl From<Range<usize>> for EntrySelector {
+    fn from(range: Range<usize>) -> Self {
+        EntrySelector::Index(range)
+    }
+}
+
+impl From<TimeSpan> for EntrySelector {
+    fn from(span: TimeSpan) -> Self {
+        EntrySelector::TimeRange(span)
+    }
+}
+
+/// Applies the affine transform `t' = anchor + (t - anchor) * factor + delta`.
+fn transform_point(t: TimePoint, factor: f64, anchor: TimePoint, delta: TimeDelta) -> TimePoint {
+    anchor + (t - anchor) * factor + delta
+}
+
+fn transform_span(span: TimeSpan, factor: f64, anchor: TimePoint, delta: TimeDelta) -> TimeSpan {
+    TimeSpan::new(
+        transform_point(span.start, factor, anchor, delta),
+        transform_point(span.end, factor, anchor, delta),
+    )
+}
+
+/// Retiming operations available on every [`SubtitleFileInterface`] implementor, so callers
+/// don't have to hand-roll the `get_subtitle_entries`/`update_subtitle_entries` dance
+/// themselves whenever they need to adjust timings.
+pub trait Retime: SubtitleFileInterface {
+    /// Shifts every subtitle entry by `delta`.
+    fn shift(&mut self, delta: TimeDelta) -> SubtitleParserResult<()> {
+        self.shift_range(EntrySelector::Index(0..usize::MAX), delta)
+    }
+
+    /// Linearly rescales every entry around `anchor` by `factor`. Useful for fixing
+    /// frame-rate mismatches, e.g. `scale(25.0 / 23.976, TimePoint::from_msecs(0))`.
+    fn scale(&mut self, factor: f64, anchor: TimePoint) -> SubtitleParserResult<()> {
+        let mut entries = self.get_subtitle_entries()?;
+
+        for entry in &mut entries {
+            entry.timespan = transform_span(entry.timespan, factor, anchor, TimeDelta::zero());
+        }
+
+        self.update_subtitle_entries(&entries)
+    }
+
+    /// Shifts only the entries selected by `selector`, which is either an index range
+    /// (`(0..3).into()`) or a `TimeSpan` window (`span.into()`).
+    fn shift_range<S: Into<EntrySelector>>(&mut self, selector: S, delta: TimeDelta) -> SubtitleParserResult<()> {
+        let selector = selector.into();
+        let mut entries = self.get_subtitle_entries()?;
+
+        for (index, entry) in entries.iter_mut().enumerate() {
+            if selector.matches(index, entry.timespan) {
+                entry.timespan = transform_span(entry.timespan, 1.0, TimePoint::from_msecs(0), delta);
+            }
+        }
+
+        self.update_subtitle_entries(&entries)
+    }
+}
+
+impl<T: SubtitleFileInterface + ?Sized> Retime for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::vtt::VttFile;
+
+    fn test_file() -> VttFile {
+        VttFile::create(vec![
+            (TimeSpan::new(TimePoint::from_msecs(1000), TimePoint::from_msecs(2000)), "line1".to_string()),
+            (TimeSpan::new(TimePoint::from_msecs(3000), TimePoint::from_msecs(4000)), "line2".to_string()),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn shift_test() {
+        let mut file = test_file();
+        file.shift(TimeDelta::from_msecs(500)).unwrap();
+
+        let entries = file.get_subtitle_entries().unwrap();
+        assert_eq!(entries[0].timespan, TimeSpan::new(TimePoint::from_msecs(1500), TimePoint::from_msecs(2500)));
+        assert_eq!(entries[1].timespan, TimeSpan::new(TimePoint::from_msecs(3500), TimePoint::from_msecs(4500)));
+    }
+
+    #[test]
+    fn scale_test() {
+        let mut file = test_file();
+        file.scale(2.0, TimePoint::from_msecs(1000)).unwrap();
+
+        let entries = file.get_subtitle_entries().unwrap();
+        assert_eq!(entries[0].timespan, TimeSpan::new(TimePoint::from_msecs(1000), TimePoint::from_msecs(3000)));
+        assert_eq!(entries[1].timespan, TimeSpan::new(TimePoint::from_msecs(5000), TimePoint::from_msecs(7000)));
+    }
+
+    #[test]
+    fn shift_range_by_index_test() {
+        let mut file = test_file();
+        file.shift_range(0..1, TimeDelta::from_msecs(500)).unwrap();
+
+        let entries = file.get_subtitle_entries().unwrap();
+        assert_eq!(entries[0].timespan, TimeSpan::new(TimePoint::from_msecs(1500), TimePoint::from_msecs(2500)));
+        assert_eq!(entries[1].timespan, TimeSpan::new(TimePoint::from_msecs(3000), TimePoint::from_msecs(4000)));
+    }
+
+    #[test]
+    fn shift_range_by_time_window_test() {
+        let mut file = test_file();
+        let window = TimeSpan::new(TimePoint::from_msecs(2500), TimePoint::from_msecs(5000));
+        file.shift_range(window, TimeDelta::from_msecs(-500)).unwrap();
+
+        let entries = file.get_subtitle_entries().unwrap();
+        assert_eq!(entries[0].timespan, TimeSpan::new(TimePoint::from_msecs(1000), TimePoint::from_msecs(2000)));
+        assert_eq!(entries[1].timespan, TimeSpan::new(TimePoint::from_msecs(2500), TimePoint::from_msecs(3500)));
+    }
+}